@@ -1,225 +1,1226 @@
 #![allow(missing_docs)]
 
-use std::any::Any;
-use std::cell::{self, RefCell};
+use std::any;
+use std::error::Error;
 use std::fmt;
-use std::rc::{Rc, Weak};
 
-trait InternalRefBase: Any {
-    fn as_any(&self) -> &dyn Any;
-    fn host_info(&self) -> Option<cell::RefMut<Box<dyn Any>>>;
-    fn set_host_info(&self, info: Option<Box<dyn Any>>);
-    fn ptr_eq(&self, other: &dyn InternalRefBase) -> bool;
+#[derive(Debug)]
+enum BorrowErrorKind {
+    AlreadyMutablyBorrowed,
+    WrongVariant,
 }
 
-#[derive(Clone)]
-pub struct InternalRef(Rc<dyn InternalRefBase>);
+/// An error returned by `HostRef::try_borrow` or `ExternRef::try_data` when
+/// the value is already mutably borrowed, or (for `ExternRef`) isn't the
+/// `Other` variant.
+#[derive(Debug)]
+pub struct BorrowError {
+    type_name: &'static str,
+    kind: BorrowErrorKind,
+}
 
-impl InternalRef {
-    pub fn is_ref<T: 'static>(&self) -> bool {
-        let r = self.0.as_any();
-        Any::is::<HostRef<T>>(r)
+impl BorrowError {
+    fn new<T: ?Sized>() -> BorrowError {
+        BorrowError {
+            type_name: any::type_name::<T>(),
+            kind: BorrowErrorKind::AlreadyMutablyBorrowed,
+        }
     }
-    pub fn get_ref<T: 'static>(&self) -> HostRef<T> {
-        let r = self.0.as_any();
-        r.downcast_ref::<HostRef<T>>()
-            .expect("reference is not T type")
-            .clone()
+
+    /// Like `new`, but for the case where the borrow failed because the
+    /// value isn't the variant that can be borrowed at all (e.g. `ExternRef`
+    /// is `Null` or `Ref` rather than `Other`), rather than because it's
+    /// concurrently borrowed.
+    fn wrong_variant<T: ?Sized>() -> BorrowError {
+        BorrowError {
+            type_name: any::type_name::<T>(),
+            kind: BorrowErrorKind::WrongVariant,
+        }
     }
 }
 
-struct AnyAndHostInfo {
-    any: Box<dyn Any>,
-    host_info: Option<Box<dyn Any>>,
+impl fmt::Display for BorrowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            BorrowErrorKind::AlreadyMutablyBorrowed => {
+                write!(f, "already mutably borrowed: {}", self.type_name)
+            }
+            BorrowErrorKind::WrongVariant => write!(f, "expected {}::Other", self.type_name),
+        }
+    }
 }
 
-#[derive(Clone)]
-pub struct OtherRef(Rc<RefCell<AnyAndHostInfo>>);
+impl Error for BorrowError {}
 
-/// Represents an opaque reference to any data within WebAssembly.
-#[derive(Clone)]
-pub enum ExternRef {
-    /// A reference to no data.
+#[derive(Debug)]
+enum BorrowMutErrorKind {
+    AlreadyBorrowed,
     Null,
-    /// A reference to data stored internally in `wasmtime`.
-    Ref(InternalRef),
-    /// A reference to data located outside of `wasmtime`.
-    Other(OtherRef),
 }
 
-impl ExternRef {
-    /// Creates a new instance of `ExternRef` from `Box<dyn Any>`.
-    pub fn new(data: Box<dyn Any>) -> Self {
-        let info = AnyAndHostInfo {
-            any: data,
-            host_info: None,
-        };
-        ExternRef::Other(OtherRef(Rc::new(RefCell::new(info))))
-    }
+/// An error returned by `HostRef::try_borrow_mut`, `ExternRef::try_host_info`
+/// or `ExternRef::try_set_host_info` when the value is already borrowed, or
+/// (for `ExternRef`) is `Null`.
+#[derive(Debug)]
+pub struct BorrowMutError {
+    type_name: &'static str,
+    kind: BorrowMutErrorKind,
+}
 
-    /// Creates a `Null` reference.
-    pub fn null() -> Self {
-        ExternRef::Null
+impl BorrowMutError {
+    fn new<T: ?Sized>() -> BorrowMutError {
+        BorrowMutError {
+            type_name: any::type_name::<T>(),
+            kind: BorrowMutErrorKind::AlreadyBorrowed,
+        }
     }
 
-    /// Returns the data stored in the reference if available.
-    /// # Panics
-    /// Panics if the variant isn't `ExternRef::Other`.
-    pub fn data(&self) -> cell::Ref<Box<dyn Any>> {
-        match self {
-            ExternRef::Other(OtherRef(r)) => cell::Ref::map(r.borrow(), |r| &r.any),
-            _ => panic!("expected ExternRef::Other"),
+    /// Like `new`, but for the case where there was nothing to borrow at all
+    /// because the `ExternRef` is `Null`, rather than because it's
+    /// concurrently borrowed.
+    fn null<T: ?Sized>() -> BorrowMutError {
+        BorrowMutError {
+            type_name: any::type_name::<T>(),
+            kind: BorrowMutErrorKind::Null,
         }
     }
+}
 
-    /// Returns true if the two `ExternRef<T>`'s point to the same value (not just
-    /// values that compare as equal).
-    pub fn ptr_eq(&self, other: &ExternRef) -> bool {
-        match (self, other) {
-            (ExternRef::Null, ExternRef::Null) => true,
-            (ExternRef::Ref(InternalRef(ref a)), ExternRef::Ref(InternalRef(ref b))) => {
-                a.ptr_eq(b.as_ref())
+impl fmt::Display for BorrowMutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            BorrowMutErrorKind::AlreadyBorrowed => {
+                write!(f, "already borrowed: {}", self.type_name)
             }
-            (ExternRef::Other(OtherRef(ref a)), ExternRef::Other(OtherRef(ref b))) => {
-                Rc::ptr_eq(a, b)
+            BorrowMutErrorKind::Null => write!(f, "null {}", self.type_name),
+        }
+    }
+}
+
+impl Error for BorrowMutError {}
+
+/// A trait for host-defined data that can be attached to a `HostRef` or
+/// `ExternRef` via `set_host_info`.
+///
+/// Implement this to receive a callback when the attached data is replaced
+/// or when the owning reference is dropped, so that any resources it holds
+/// (file handles, GPU objects, host-side tables, etc.) can be released.
+///
+/// When the `parallel-ref` feature is enabled, `HostRef`/`ExternRef` become
+/// `Send + Sync`, so host info must be as well.
+#[cfg(not(feature = "parallel-ref"))]
+pub trait HostInfo {
+    /// Called when this host info is replaced by a new value, or when the
+    /// reference it is attached to is dropped.
+    fn finalize(&mut self) {}
+}
+
+#[cfg(feature = "parallel-ref")]
+pub trait HostInfo: Send + Sync {
+    /// Called when this host info is replaced by a new value, or when the
+    /// reference it is attached to is dropped.
+    fn finalize(&mut self) {}
+}
+
+#[cfg(not(feature = "parallel-ref"))]
+pub use single_threaded::{ExternRef, HostRef, InternalRef, OtherRef, Ref, RefMut};
+
+#[cfg(feature = "parallel-ref")]
+pub use parallel::{ExternRef, HostRef, InternalRef, OtherRef, Ref, RefMut};
+
+/// The default, `Rc`/`RefCell`-based implementation of `HostRef`/`ExternRef`.
+///
+/// This is `!Send + !Sync`, but pays no synchronization cost, so it is used
+/// unless the `parallel-ref` feature asks for the thread-safe variant below.
+#[cfg(not(feature = "parallel-ref"))]
+mod single_threaded {
+    use super::{BorrowError, BorrowMutError, HostInfo};
+    use std::any::Any;
+    use std::cell::{self, RefCell};
+    use std::fmt;
+    use std::ops::{Deref, DerefMut};
+    use std::rc::{Rc, Weak};
+
+    /// A guard for a shared borrow of a [`HostRef`] or [`ExternRef`].
+    ///
+    /// Mirrors `std::cell::Ref`'s `map`/`map_split` so a borrow can be
+    /// projected down to a field or a downcast of the contained value
+    /// without cloning or re-borrowing.
+    pub struct Ref<'a, T: ?Sized>(cell::Ref<'a, T>);
+
+    impl<'a, T: ?Sized> Deref for Ref<'a, T> {
+        type Target = T;
+        fn deref(&self) -> &T {
+            &self.0
+        }
+    }
+
+    impl<'a, T: ?Sized> Ref<'a, T> {
+        /// Makes a new `Ref` for a component of the borrowed data.
+        pub fn map<U: ?Sized, F>(orig: Ref<'a, T>, f: F) -> Ref<'a, U>
+        where
+            F: FnOnce(&T) -> &U,
+        {
+            Ref(cell::Ref::map(orig.0, f))
+        }
+
+        /// Splits a `Ref` into two disjoint references to different
+        /// components of the borrowed data.
+        pub fn map_split<U: ?Sized, V: ?Sized, F>(orig: Ref<'a, T>, f: F) -> (Ref<'a, U>, Ref<'a, V>)
+        where
+            F: FnOnce(&T) -> (&U, &V),
+        {
+            let (a, b) = cell::Ref::map_split(orig.0, f);
+            (Ref(a), Ref(b))
+        }
+    }
+
+    /// A guard for a unique borrow of a [`HostRef`] or [`ExternRef`].
+    ///
+    /// Mirrors `std::cell::RefMut`'s `map`/`map_split` so a borrow can be
+    /// projected down to a field or a downcast of the contained value
+    /// without cloning or re-borrowing.
+    pub struct RefMut<'a, T: ?Sized>(cell::RefMut<'a, T>);
+
+    impl<'a, T: ?Sized> Deref for RefMut<'a, T> {
+        type Target = T;
+        fn deref(&self) -> &T {
+            &self.0
+        }
+    }
+
+    impl<'a, T: ?Sized> DerefMut for RefMut<'a, T> {
+        fn deref_mut(&mut self) -> &mut T {
+            &mut self.0
+        }
+    }
+
+    impl<'a, T: ?Sized> RefMut<'a, T> {
+        /// Makes a new `RefMut` for a component of the borrowed data.
+        pub fn map<U: ?Sized, F>(orig: RefMut<'a, T>, f: F) -> RefMut<'a, U>
+        where
+            F: FnOnce(&mut T) -> &mut U,
+        {
+            RefMut(cell::RefMut::map(orig.0, f))
+        }
+
+        /// Splits a `RefMut` into two disjoint mutable references to
+        /// different components of the borrowed data.
+        pub fn map_split<U: ?Sized, V: ?Sized, F>(
+            orig: RefMut<'a, T>,
+            f: F,
+        ) -> (RefMut<'a, U>, RefMut<'a, V>)
+        where
+            F: FnOnce(&mut T) -> (&mut U, &mut V),
+        {
+            let (a, b) = cell::RefMut::map_split(orig.0, f);
+            (RefMut(a), RefMut(b))
+        }
+    }
+
+    trait InternalRefBase: Any {
+        fn as_any(&self) -> &dyn Any;
+        fn try_host_info(
+            &self,
+        ) -> Result<Option<cell::RefMut<'_, Box<dyn HostInfo>>>, BorrowMutError>;
+        fn try_set_host_info(&self, info: Option<Box<dyn HostInfo>>) -> Result<(), BorrowMutError>;
+        fn ptr_eq(&self, other: &dyn InternalRefBase) -> bool;
+    }
+
+    #[derive(Clone)]
+    pub struct InternalRef(Rc<dyn InternalRefBase>);
+
+    impl InternalRef {
+        pub fn is_ref<T: 'static>(&self) -> bool {
+            let r = self.0.as_any();
+            <dyn Any>::is::<HostRef<T>>(r)
+        }
+        pub fn get_ref<T: 'static>(&self) -> HostRef<T> {
+            let r = self.0.as_any();
+            r.downcast_ref::<HostRef<T>>()
+                .expect("reference is not T type")
+                .clone()
+        }
+    }
+
+    struct AnyAndHostInfo {
+        any: Box<dyn Any>,
+        host_info: Option<Box<dyn HostInfo>>,
+    }
+
+    impl Drop for AnyAndHostInfo {
+        fn drop(&mut self) {
+            if let Some(info) = &mut self.host_info {
+                info.finalize();
             }
-            _ => false,
         }
     }
 
-    /// Returns a mutable reference to the host information if available.
-    /// # Panics
-    /// Panics if `ExternRef` is already borrowed or `ExternRef` is `Null`.
-    pub fn host_info(&self) -> Option<cell::RefMut<Box<dyn Any>>> {
-        match self {
-            ExternRef::Null => panic!("null"),
-            ExternRef::Ref(r) => r.0.host_info(),
-            ExternRef::Other(r) => {
-                let info = cell::RefMut::map(r.0.borrow_mut(), |b| &mut b.host_info);
-                if info.is_none() {
-                    return None;
+    #[derive(Clone)]
+    pub struct OtherRef(Rc<RefCell<AnyAndHostInfo>>);
+
+    /// Represents an opaque reference to any data within WebAssembly.
+    #[derive(Clone)]
+    pub enum ExternRef {
+        /// A reference to no data.
+        Null,
+        /// A reference to data stored internally in `wasmtime`.
+        Ref(InternalRef),
+        /// A reference to data located outside of `wasmtime`.
+        Other(OtherRef),
+    }
+
+    impl ExternRef {
+        /// Creates a new instance of `ExternRef` from `Box<dyn Any>`.
+        pub fn new(data: Box<dyn Any>) -> Self {
+            let info = AnyAndHostInfo {
+                any: data,
+                host_info: None,
+            };
+            ExternRef::Other(OtherRef(Rc::new(RefCell::new(info))))
+        }
+
+        /// Creates a `Null` reference.
+        pub fn null() -> Self {
+            ExternRef::Null
+        }
+
+        /// Returns the data stored in the reference if available, or an error if
+        /// the variant isn't `ExternRef::Other` or the value is already mutably
+        /// borrowed.
+        pub fn try_data(&self) -> Result<Ref<'_, Box<dyn Any>>, BorrowError> {
+            match self {
+                ExternRef::Other(OtherRef(r)) => r
+                    .try_borrow()
+                    .map(|r| Ref(cell::Ref::map(r, |r| &r.any)))
+                    .map_err(|_| BorrowError::new::<ExternRef>()),
+                _ => Err(BorrowError::wrong_variant::<ExternRef>()),
+            }
+        }
+
+        /// Returns the data stored in the reference if available.
+        /// # Panics
+        /// Panics if the variant isn't `ExternRef::Other`, or if it is already
+        /// mutably borrowed.
+        pub fn data(&self) -> Ref<'_, Box<dyn Any>> {
+            self.try_data().unwrap_or_else(|e| panic!("{}", e))
+        }
+
+        /// Returns true if the two `ExternRef<T>`'s point to the same value (not just
+        /// values that compare as equal).
+        pub fn ptr_eq(&self, other: &ExternRef) -> bool {
+            match (self, other) {
+                (ExternRef::Null, ExternRef::Null) => true,
+                (ExternRef::Ref(InternalRef(ref a)), ExternRef::Ref(InternalRef(ref b))) => {
+                    a.ptr_eq(b.as_ref())
+                }
+                (ExternRef::Other(OtherRef(ref a)), ExternRef::Other(OtherRef(ref b))) => {
+                    Rc::ptr_eq(a, b)
+                }
+                _ => false,
+            }
+        }
+
+        /// Returns a mutable reference to the host information if available, or
+        /// an error if `ExternRef` is already borrowed or is `Null`.
+        pub fn try_host_info(&self) -> Result<Option<RefMut<'_, Box<dyn HostInfo>>>, BorrowMutError> {
+            match self {
+                ExternRef::Null => Err(BorrowMutError::null::<ExternRef>()),
+                ExternRef::Ref(r) => Ok(r.0.try_host_info()?.map(RefMut)),
+                ExternRef::Other(r) => {
+                    let info = cell::RefMut::map(
+                        r.0.try_borrow_mut()
+                            .map_err(|_| BorrowMutError::new::<ExternRef>())?,
+                        |b| &mut b.host_info,
+                    );
+                    if info.is_none() {
+                        return Ok(None);
+                    }
+                    Ok(Some(RefMut(cell::RefMut::map(info, |info| {
+                        info.as_mut().unwrap()
+                    }))))
+                }
+            }
+        }
+
+        /// Returns a mutable reference to the host information if available.
+        /// # Panics
+        /// Panics if `ExternRef` is already borrowed or `ExternRef` is `Null`.
+        pub fn host_info(&self) -> Option<RefMut<'_, Box<dyn HostInfo>>> {
+            self.try_host_info().unwrap_or_else(|e| panic!("{}", e))
+        }
+
+        /// Sets the host information for an `ExternRef`, or returns an error if
+        /// `ExternRef` is already borrowed or is `Null`.
+        ///
+        /// If a host info was already attached, its `finalize()` is called
+        /// before it is replaced.
+        pub fn try_set_host_info(
+            &self,
+            info: Option<Box<dyn HostInfo>>,
+        ) -> Result<(), BorrowMutError> {
+            match self {
+                ExternRef::Null => Err(BorrowMutError::null::<ExternRef>()),
+                ExternRef::Ref(r) => r.0.try_set_host_info(info),
+                ExternRef::Other(r) => {
+                    let mut slot = r
+                        .0
+                        .try_borrow_mut()
+                        .map_err(|_| BorrowMutError::new::<ExternRef>())?;
+                    if let Some(old) = &mut slot.host_info {
+                        old.finalize();
+                    }
+                    slot.host_info = info;
+                    Ok(())
                 }
-                Some(cell::RefMut::map(info, |info| info.as_mut().unwrap()))
             }
         }
+
+        /// Sets the host information for an `ExternRef`.
+        ///
+        /// If a host info was already attached, its `finalize()` is called
+        /// before it is replaced.
+        /// # Panics
+        /// Panics if `ExternRef` is already borrowed or `ExternRef` is `Null`.
+        pub fn set_host_info(&self, info: Option<Box<dyn HostInfo>>) {
+            self.try_set_host_info(info)
+                .unwrap_or_else(|e| panic!("{}", e))
+        }
     }
 
-    /// Sets the host information for an `ExternRef`.
-    /// # Panics
-    /// Panics if `ExternRef` is already borrowed or `ExternRef` is `Null`.
-    pub fn set_host_info(&self, info: Option<Box<dyn Any>>) {
-        match self {
-            ExternRef::Null => panic!("null"),
-            ExternRef::Ref(r) => r.0.set_host_info(info),
-            ExternRef::Other(r) => {
-                r.0.borrow_mut().host_info = info;
+    impl fmt::Debug for ExternRef {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ExternRef::Null => write!(f, "null"),
+                ExternRef::Ref(_) => write!(f, "externref"),
+                ExternRef::Other(_) => write!(f, "other ref"),
             }
         }
     }
-}
 
-impl fmt::Debug for ExternRef {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            ExternRef::Null => write!(f, "null"),
-            ExternRef::Ref(_) => write!(f, "externref"),
-            ExternRef::Other(_) => write!(f, "other ref"),
+    struct ContentBox<T> {
+        content: T,
+        host_info: Option<Box<dyn HostInfo>>,
+        externref_data: Weak<dyn InternalRefBase>,
+    }
+
+    impl<T> Drop for ContentBox<T> {
+        fn drop(&mut self) {
+            if let Some(info) = &mut self.host_info {
+                info.finalize();
+            }
         }
     }
-}
 
-struct ContentBox<T> {
-    content: T,
-    host_info: Option<Box<dyn Any>>,
-    externref_data: Weak<dyn InternalRefBase>,
-}
+    /// Represents a piece of data located in the host environment.
+    pub struct HostRef<T>(Rc<RefCell<ContentBox<T>>>);
 
-/// Represents a piece of data located in the host environment.
-pub struct HostRef<T>(Rc<RefCell<ContentBox<T>>>);
+    impl<T: 'static> HostRef<T> {
+        /// Creates a new `HostRef<T>` from `T`.
+        pub fn new(item: T) -> HostRef<T> {
+            let externref_data: Weak<HostRef<T>> = Weak::new();
+            let content = ContentBox {
+                content: item,
+                host_info: None,
+                externref_data,
+            };
+            HostRef(Rc::new(RefCell::new(content)))
+        }
+
+        /// Immutably borrows the wrapped data, returning an error instead of
+        /// panicking if the value is currently mutably borrowed.
+        pub fn try_borrow(&self) -> Result<Ref<'_, T>, BorrowError> {
+            self.0
+                .try_borrow()
+                .map(|b| Ref(cell::Ref::map(b, |b| &b.content)))
+                .map_err(|_| BorrowError::new::<HostRef<T>>())
+        }
+
+        /// Immutably borrows the wrapped data.
+        /// # Panics
+        /// Panics if the value is currently mutably borrowed.
+        pub fn borrow(&self) -> Ref<'_, T> {
+            self.try_borrow().unwrap_or_else(|e| panic!("{}", e))
+        }
 
-impl<T: 'static> HostRef<T> {
-    /// Creates a new `HostRef<T>` from `T`.
-    pub fn new(item: T) -> HostRef<T> {
-        let externref_data: Weak<HostRef<T>> = Weak::new();
-        let content = ContentBox {
-            content: item,
-            host_info: None,
-            externref_data,
-        };
-        HostRef(Rc::new(RefCell::new(content)))
+        /// Mutably borrows the wrapped data, returning an error instead of
+        /// panicking if the `HostRef<T>` is already borrowed.
+        pub fn try_borrow_mut(&self) -> Result<RefMut<'_, T>, BorrowMutError> {
+            self.0
+                .try_borrow_mut()
+                .map(|b| RefMut(cell::RefMut::map(b, |b| &mut b.content)))
+                .map_err(|_| BorrowMutError::new::<HostRef<T>>())
+        }
+
+        /// Mutably borrows the wrapped data.
+        /// # Panics
+        /// Panics if the `HostRef<T>` is already borrowed.
+        pub fn borrow_mut(&self) -> RefMut<'_, T> {
+            self.try_borrow_mut().unwrap_or_else(|e| panic!("{}", e))
+        }
+
+        /// Returns true if the two `HostRef<T>`'s point to the same value (not just
+        /// values that compare as equal).
+        pub fn ptr_eq(&self, other: &HostRef<T>) -> bool {
+            Rc::ptr_eq(&self.0, &other.0)
+        }
+
+        /// Returns an opaque reference to the wrapped data in the form of
+        /// an `ExternRef`.
+        /// # Panics
+        /// Panics if `HostRef<T>` is already borrowed.
+        pub fn externref(&self) -> ExternRef {
+            let r = self.0.borrow_mut().externref_data.upgrade();
+            if let Some(r) = r {
+                return ExternRef::Ref(InternalRef(r));
+            }
+            let externref_data: Rc<dyn InternalRefBase> = Rc::new(self.clone());
+            self.0.borrow_mut().externref_data = Rc::downgrade(&externref_data);
+            ExternRef::Ref(InternalRef(externref_data))
+        }
     }
 
-    /// Immutably borrows the wrapped data.
-    /// # Panics
-    /// Panics if the value is currently mutably borrowed.
-    pub fn borrow(&self) -> cell::Ref<T> {
-        cell::Ref::map(self.0.borrow(), |b| &b.content)
+    impl<T: 'static> InternalRefBase for HostRef<T> {
+        fn ptr_eq(&self, other: &dyn InternalRefBase) -> bool {
+            if let Some(other) = other.as_any().downcast_ref() {
+                self.ptr_eq(other)
+            } else {
+                false
+            }
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn try_host_info(
+            &self,
+        ) -> Result<Option<cell::RefMut<'_, Box<dyn HostInfo>>>, BorrowMutError> {
+            let info = cell::RefMut::map(
+                self.0
+                    .try_borrow_mut()
+                    .map_err(|_| BorrowMutError::new::<T>())?,
+                |b| &mut b.host_info,
+            );
+            if info.is_none() {
+                return Ok(None);
+            }
+            Ok(Some(cell::RefMut::map(info, |info| {
+                info.as_mut().unwrap()
+            })))
+        }
+
+        fn try_set_host_info(&self, info: Option<Box<dyn HostInfo>>) -> Result<(), BorrowMutError> {
+            let mut content = self
+                .0
+                .try_borrow_mut()
+                .map_err(|_| BorrowMutError::new::<T>())?;
+            if let Some(old) = &mut content.host_info {
+                old.finalize();
+            }
+            content.host_info = info;
+            Ok(())
+        }
     }
 
-    /// Mutably borrows the wrapped data.
-    /// # Panics
-    /// Panics if the `HostRef<T>` is already borrowed.
-    pub fn borrow_mut(&self) -> cell::RefMut<T> {
-        cell::RefMut::map(self.0.borrow_mut(), |b| &mut b.content)
+    impl<T> Clone for HostRef<T> {
+        fn clone(&self) -> HostRef<T> {
+            HostRef(self.0.clone())
+        }
     }
 
-    /// Returns true if the two `HostRef<T>`'s point to the same value (not just
-    /// values that compare as equal).
-    pub fn ptr_eq(&self, other: &HostRef<T>) -> bool {
-        Rc::ptr_eq(&self.0, &other.0)
+    impl<T: fmt::Debug> fmt::Debug for HostRef<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "Ref(")?;
+            self.0.borrow().content.fmt(f)?;
+            write!(f, ")")
+        }
     }
 
-    /// Returns an opaque reference to the wrapped data in the form of
-    /// an `ExternRef`.
-    /// # Panics
-    /// Panics if `HostRef<T>` is already mutably borrowed.
-    pub fn externref(&self) -> ExternRef {
-        let r = self.0.borrow_mut().externref_data.upgrade();
-        if let Some(r) = r {
-            return ExternRef::Ref(InternalRef(r));
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::cell::Cell;
+
+        struct NoOpInfo;
+        impl HostInfo for NoOpInfo {}
+
+        struct FinalizeCounter(Rc<Cell<u32>>);
+        impl HostInfo for FinalizeCounter {
+            fn finalize(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        #[test]
+        fn finalize_fires_once_when_last_reference_drops() {
+            let counter = Rc::new(Cell::new(0));
+            let r = ExternRef::new(Box::new(42u32));
+            r.set_host_info(Some(Box::new(FinalizeCounter(counter.clone()))));
+            assert_eq!(counter.get(), 0);
+            drop(r);
+            assert_eq!(counter.get(), 1);
+        }
+
+        #[test]
+        fn finalize_fires_once_when_host_info_is_replaced() {
+            let counter = Rc::new(Cell::new(0));
+            let r = ExternRef::new(Box::new(42u32));
+            r.set_host_info(Some(Box::new(FinalizeCounter(counter.clone()))));
+            r.set_host_info(Some(Box::new(NoOpInfo)));
+            assert_eq!(counter.get(), 1);
+        }
+
+        #[test]
+        fn set_host_info_none_only_finalizes_existing_info() {
+            let counter = Rc::new(Cell::new(0));
+            let r = ExternRef::new(Box::new(42u32));
+            r.set_host_info(Some(Box::new(FinalizeCounter(counter.clone()))));
+            r.set_host_info(None);
+            assert_eq!(counter.get(), 1);
+            // Nothing is attached now, so clearing again must not finalize
+            // anything a second time.
+            r.set_host_info(None);
+            assert_eq!(counter.get(), 1);
+        }
+
+        #[test]
+        fn host_ref_try_borrow_mut_fails_while_borrowed() {
+            let r = HostRef::new(1);
+            let _guard = r.borrow();
+            assert!(r.try_borrow_mut().is_err());
+        }
+
+        #[test]
+        fn host_ref_try_borrow_fails_while_mutably_borrowed() {
+            let r = HostRef::new(1);
+            let _guard = r.borrow_mut();
+            assert!(r.try_borrow().is_err());
+        }
+
+        #[test]
+        fn extern_ref_try_data_fails_for_null() {
+            let r = ExternRef::null();
+            assert!(r.try_data().is_err());
+        }
+
+        #[test]
+        fn extern_ref_try_data_fails_for_ref_variant() {
+            let r = HostRef::new(1).externref();
+            assert!(matches!(r, ExternRef::Ref(_)));
+            assert!(r.try_data().is_err());
+        }
+
+        #[test]
+        fn extern_ref_try_host_info_fails_for_null() {
+            let r = ExternRef::null();
+            assert!(r.try_host_info().is_err());
+        }
+
+        #[test]
+        fn extern_ref_try_data_fails_while_other_is_mutably_borrowed() {
+            let r = ExternRef::new(Box::new(1u32));
+            r.set_host_info(Some(Box::new(NoOpInfo)));
+            let _guard = r.host_info();
+            assert!(r.try_data().is_err());
         }
-        let externref_data: Rc<dyn InternalRefBase> = Rc::new(self.clone());
-        self.0.borrow_mut().externref_data = Rc::downgrade(&externref_data);
-        ExternRef::Ref(InternalRef(externref_data))
     }
 }
 
-impl<T: 'static> InternalRefBase for HostRef<T> {
-    fn ptr_eq(&self, other: &dyn InternalRefBase) -> bool {
-        if let Some(other) = other.as_any().downcast_ref() {
-            self.ptr_eq(other)
-        } else {
-            false
+/// A `Send + Sync` implementation of `HostRef`/`ExternRef`, enabled by the
+/// `parallel-ref` feature for embedders that move references between
+/// threads or share a `Store` across a thread pool.
+///
+/// This swaps `Rc` for `Arc` and `RefCell` for `TrustCell`, a cell backed by
+/// a single `AtomicUsize` borrow flag, giving the same single-threaded
+/// `RefCell` semantics (one writer xor many readers) without requiring a
+/// lock.
+#[cfg(feature = "parallel-ref")]
+mod parallel {
+    use super::{BorrowError, BorrowMutError, HostInfo};
+    use std::any::Any;
+    use std::cell::UnsafeCell;
+    use std::fmt;
+    use std::ops::{Deref, DerefMut};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Weak};
+
+    /// Sentinel borrow-flag value recorded while a unique (mutable) borrow
+    /// is outstanding. Any other value is the number of live shared borrows.
+    const WRITING: usize = usize::MAX;
+
+    /// A lightweight `Send + Sync` cell with `RefCell`-like borrow checking,
+    /// backed by a single atomic borrow-flag instead of requiring a lock.
+    struct TrustCell<T> {
+        flag: AtomicUsize,
+        value: UnsafeCell<T>,
+    }
+
+    // Safety: `TrustCell` hands out a `&T` to any number of borrowing
+    // threads at once for shared borrows (hence `T: Sync`), or a `&mut T` to
+    // exactly one thread for a unique borrow (hence `T: Send` suffices for
+    // `Send`), matching `RwLock`'s bounds.
+    unsafe impl<T: Send> Send for TrustCell<T> {}
+    unsafe impl<T: Send + Sync> Sync for TrustCell<T> {}
+
+    impl<T> TrustCell<T> {
+        fn new(value: T) -> TrustCell<T> {
+            TrustCell {
+                flag: AtomicUsize::new(0),
+                value: UnsafeCell::new(value),
+            }
+        }
+
+        fn try_borrow(&self) -> Result<Ref<'_, T>, BorrowError> {
+            loop {
+                let flag = self.flag.load(Ordering::Acquire);
+                if flag == WRITING {
+                    return Err(BorrowError::new::<T>());
+                }
+                if self
+                    .flag
+                    .compare_exchange_weak(flag, flag + 1, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+                {
+                    return Ok(Ref {
+                        value: unsafe { &*self.value.get() },
+                        borrow: BorrowRef { flag: &self.flag },
+                    });
+                }
+            }
+        }
+
+        fn borrow(&self) -> Ref<'_, T> {
+            self.try_borrow().expect("already mutably borrowed")
+        }
+
+        fn try_borrow_mut(&self) -> Result<RefMut<'_, T>, BorrowMutError> {
+            if self
+                .flag
+                .compare_exchange(0, WRITING, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+            {
+                Ok(RefMut {
+                    value: unsafe { &mut *self.value.get() },
+                    borrow: BorrowRefMut {
+                        live: Arc::new(FlagResetOnDrop { flag: &self.flag }),
+                    },
+                })
+            } else {
+                Err(BorrowMutError::new::<T>())
+            }
+        }
+
+        fn borrow_mut(&self) -> RefMut<'_, T> {
+            self.try_borrow_mut().expect("already borrowed")
         }
     }
 
-    fn as_any(&self) -> &dyn Any {
-        self
+    struct BorrowRef<'a> {
+        flag: &'a AtomicUsize,
     }
 
-    fn host_info(&self) -> Option<cell::RefMut<Box<dyn Any>>> {
-        let info = cell::RefMut::map(self.0.borrow_mut(), |b| &mut b.host_info);
-        if info.is_none() {
-            return None;
+    impl<'a> Clone for BorrowRef<'a> {
+        fn clone(&self) -> BorrowRef<'a> {
+            // `map_split` hands out a second shared borrow derived from this
+            // one, so the live-borrow count has to grow to match.
+            self.flag.fetch_add(1, Ordering::Acquire);
+            BorrowRef { flag: self.flag }
         }
-        Some(cell::RefMut::map(info, |info| info.as_mut().unwrap()))
     }
 
-    fn set_host_info(&self, info: Option<Box<dyn Any>>) {
-        self.0.borrow_mut().host_info = info;
+    impl<'a> Drop for BorrowRef<'a> {
+        fn drop(&mut self) {
+            self.flag.fetch_sub(1, Ordering::Release);
+        }
     }
-}
 
-impl<T> Clone for HostRef<T> {
-    fn clone(&self) -> HostRef<T> {
-        HostRef(self.0.clone())
+    /// Resets a unique-borrow flag to "unused" in its `Drop` impl. Wrapping
+    /// this in an `Arc` means the reset runs exactly once, the instant the
+    /// last clone is deallocated -- `Arc`'s own atomic refcounting decides
+    /// that moment, so there is no separate count to race against.
+    struct FlagResetOnDrop<'a> {
+        flag: &'a AtomicUsize,
     }
-}
 
-impl<T: fmt::Debug> fmt::Debug for HostRef<T> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Ref(")?;
-        self.0.borrow().content.fmt(f)?;
-        write!(f, ")")
+    impl<'a> Drop for FlagResetOnDrop<'a> {
+        fn drop(&mut self) {
+            self.flag.store(0, Ordering::Release);
+        }
+    }
+
+    /// A guard for a unique borrow that has (potentially) been split by
+    /// `map_split`. The underlying flag is only released once every clone
+    /// born from the same borrow has been dropped.
+    struct BorrowRefMut<'a> {
+        live: Arc<FlagResetOnDrop<'a>>,
+    }
+
+    impl<'a> Clone for BorrowRefMut<'a> {
+        fn clone(&self) -> BorrowRefMut<'a> {
+            BorrowRefMut {
+                live: self.live.clone(),
+            }
+        }
+    }
+
+    /// A guard for a shared borrow of a [`HostRef`] or [`ExternRef`].
+    /// Mirrors `std::cell::Ref`.
+    pub struct Ref<'a, T: ?Sized> {
+        value: &'a T,
+        borrow: BorrowRef<'a>,
+    }
+
+    impl<'a, T: ?Sized> Deref for Ref<'a, T> {
+        type Target = T;
+        fn deref(&self) -> &T {
+            self.value
+        }
+    }
+
+    impl<'a, T: ?Sized> Ref<'a, T> {
+        /// Makes a new `Ref` for a component of the borrowed data.
+        pub fn map<U: ?Sized, F>(orig: Ref<'a, T>, f: F) -> Ref<'a, U>
+        where
+            F: FnOnce(&T) -> &U,
+        {
+            Ref {
+                value: f(orig.value),
+                borrow: orig.borrow,
+            }
+        }
+
+        /// Splits a `Ref` into two disjoint references to different
+        /// components of the borrowed data.
+        pub fn map_split<U: ?Sized, V: ?Sized, F>(orig: Ref<'a, T>, f: F) -> (Ref<'a, U>, Ref<'a, V>)
+        where
+            F: FnOnce(&T) -> (&U, &V),
+        {
+            let (a, b) = f(orig.value);
+            let borrow = orig.borrow.clone();
+            (
+                Ref {
+                    value: a,
+                    borrow: orig.borrow,
+                },
+                Ref { value: b, borrow },
+            )
+        }
+    }
+
+    /// A guard for a unique borrow of a [`HostRef`] or [`ExternRef`].
+    /// Mirrors `std::cell::RefMut`.
+    pub struct RefMut<'a, T: ?Sized> {
+        value: &'a mut T,
+        borrow: BorrowRefMut<'a>,
+    }
+
+    impl<'a, T: ?Sized> Deref for RefMut<'a, T> {
+        type Target = T;
+        fn deref(&self) -> &T {
+            self.value
+        }
+    }
+
+    impl<'a, T: ?Sized> DerefMut for RefMut<'a, T> {
+        fn deref_mut(&mut self) -> &mut T {
+            self.value
+        }
+    }
+
+    impl<'a, T: ?Sized> RefMut<'a, T> {
+        /// Makes a new `RefMut` for a component of the borrowed data.
+        pub fn map<U: ?Sized, F>(orig: RefMut<'a, T>, f: F) -> RefMut<'a, U>
+        where
+            F: FnOnce(&mut T) -> &mut U,
+        {
+            RefMut {
+                value: f(orig.value),
+                borrow: orig.borrow,
+            }
+        }
+
+        /// Splits a `RefMut` into two disjoint mutable references to
+        /// different components of the borrowed data.
+        pub fn map_split<U: ?Sized, V: ?Sized, F>(
+            orig: RefMut<'a, T>,
+            f: F,
+        ) -> (RefMut<'a, U>, RefMut<'a, V>)
+        where
+            F: FnOnce(&mut T) -> (&mut U, &mut V),
+        {
+            let (a, b) = f(orig.value);
+            let borrow = orig.borrow.clone();
+            (
+                RefMut {
+                    value: a,
+                    borrow: orig.borrow,
+                },
+                RefMut { value: b, borrow },
+            )
+        }
+    }
+
+    trait InternalRefBase: Any + Send + Sync {
+        fn as_any(&self) -> &dyn Any;
+        fn try_host_info(&self) -> Result<Option<RefMut<'_, Box<dyn HostInfo>>>, BorrowMutError>;
+        fn try_set_host_info(&self, info: Option<Box<dyn HostInfo>>) -> Result<(), BorrowMutError>;
+        fn ptr_eq(&self, other: &dyn InternalRefBase) -> bool;
+    }
+
+    #[derive(Clone)]
+    pub struct InternalRef(Arc<dyn InternalRefBase>);
+
+    impl InternalRef {
+        pub fn is_ref<T: 'static + Send + Sync>(&self) -> bool {
+            let r = self.0.as_any();
+            <dyn Any>::is::<HostRef<T>>(r)
+        }
+        pub fn get_ref<T: 'static + Send + Sync>(&self) -> HostRef<T> {
+            let r = self.0.as_any();
+            r.downcast_ref::<HostRef<T>>()
+                .expect("reference is not T type")
+                .clone()
+        }
+    }
+
+    struct AnyAndHostInfo {
+        any: Box<dyn Any + Send + Sync>,
+        host_info: Option<Box<dyn HostInfo>>,
+    }
+
+    impl Drop for AnyAndHostInfo {
+        fn drop(&mut self) {
+            if let Some(info) = &mut self.host_info {
+                info.finalize();
+            }
+        }
+    }
+
+    #[derive(Clone)]
+    pub struct OtherRef(Arc<TrustCell<AnyAndHostInfo>>);
+
+    /// Represents an opaque reference to any data within WebAssembly.
+    #[derive(Clone)]
+    pub enum ExternRef {
+        /// A reference to no data.
+        Null,
+        /// A reference to data stored internally in `wasmtime`.
+        Ref(InternalRef),
+        /// A reference to data located outside of `wasmtime`.
+        Other(OtherRef),
+    }
+
+    impl ExternRef {
+        /// Creates a new instance of `ExternRef` from `Box<dyn Any + Send + Sync>`.
+        pub fn new(data: Box<dyn Any + Send + Sync>) -> Self {
+            let info = AnyAndHostInfo {
+                any: data,
+                host_info: None,
+            };
+            ExternRef::Other(OtherRef(Arc::new(TrustCell::new(info))))
+        }
+
+        /// Creates a `Null` reference.
+        pub fn null() -> Self {
+            ExternRef::Null
+        }
+
+        /// Returns the data stored in the reference if available, or an error if
+        /// the variant isn't `ExternRef::Other` or the value is already mutably
+        /// borrowed.
+        pub fn try_data(&self) -> Result<Ref<'_, Box<dyn Any + Send + Sync>>, BorrowError> {
+            match self {
+                ExternRef::Other(OtherRef(r)) => r.try_borrow().map(|r| Ref::map(r, |r| &r.any)),
+                _ => Err(BorrowError::wrong_variant::<ExternRef>()),
+            }
+        }
+
+        /// Returns the data stored in the reference if available.
+        /// # Panics
+        /// Panics if the variant isn't `ExternRef::Other`, or if it is already
+        /// mutably borrowed.
+        pub fn data(&self) -> Ref<'_, Box<dyn Any + Send + Sync>> {
+            self.try_data().unwrap_or_else(|e| panic!("{}", e))
+        }
+
+        /// Returns true if the two `ExternRef<T>`'s point to the same value (not just
+        /// values that compare as equal).
+        pub fn ptr_eq(&self, other: &ExternRef) -> bool {
+            match (self, other) {
+                (ExternRef::Null, ExternRef::Null) => true,
+                (ExternRef::Ref(InternalRef(ref a)), ExternRef::Ref(InternalRef(ref b))) => {
+                    a.ptr_eq(b.as_ref())
+                }
+                (ExternRef::Other(OtherRef(ref a)), ExternRef::Other(OtherRef(ref b))) => {
+                    Arc::ptr_eq(a, b)
+                }
+                _ => false,
+            }
+        }
+
+        /// Returns a mutable reference to the host information if available, or
+        /// an error if `ExternRef` is already borrowed or is `Null`.
+        pub fn try_host_info(
+            &self,
+        ) -> Result<Option<RefMut<'_, Box<dyn HostInfo>>>, BorrowMutError> {
+            match self {
+                ExternRef::Null => Err(BorrowMutError::null::<ExternRef>()),
+                ExternRef::Ref(r) => r.0.try_host_info(),
+                ExternRef::Other(r) => {
+                    let info = RefMut::map(r.0.try_borrow_mut()?, |b| &mut b.host_info);
+                    if info.is_none() {
+                        return Ok(None);
+                    }
+                    Ok(Some(RefMut::map(info, |info| info.as_mut().unwrap())))
+                }
+            }
+        }
+
+        /// Returns a mutable reference to the host information if available.
+        /// # Panics
+        /// Panics if `ExternRef` is already borrowed or `ExternRef` is `Null`.
+        pub fn host_info(&self) -> Option<RefMut<'_, Box<dyn HostInfo>>> {
+            self.try_host_info().unwrap_or_else(|e| panic!("{}", e))
+        }
+
+        /// Sets the host information for an `ExternRef`, or returns an error if
+        /// `ExternRef` is already borrowed or is `Null`.
+        ///
+        /// If a host info was already attached, its `finalize()` is called
+        /// before it is replaced.
+        pub fn try_set_host_info(
+            &self,
+            info: Option<Box<dyn HostInfo>>,
+        ) -> Result<(), BorrowMutError> {
+            match self {
+                ExternRef::Null => Err(BorrowMutError::null::<ExternRef>()),
+                ExternRef::Ref(r) => r.0.try_set_host_info(info),
+                ExternRef::Other(r) => {
+                    let mut slot = r.0.try_borrow_mut()?;
+                    if let Some(old) = &mut slot.host_info {
+                        old.finalize();
+                    }
+                    slot.host_info = info;
+                    Ok(())
+                }
+            }
+        }
+
+        /// Sets the host information for an `ExternRef`.
+        ///
+        /// If a host info was already attached, its `finalize()` is called
+        /// before it is replaced.
+        /// # Panics
+        /// Panics if `ExternRef` is already borrowed or `ExternRef` is `Null`.
+        pub fn set_host_info(&self, info: Option<Box<dyn HostInfo>>) {
+            self.try_set_host_info(info)
+                .unwrap_or_else(|e| panic!("{}", e))
+        }
+    }
+
+    impl fmt::Debug for ExternRef {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ExternRef::Null => write!(f, "null"),
+                ExternRef::Ref(_) => write!(f, "externref"),
+                ExternRef::Other(_) => write!(f, "other ref"),
+            }
+        }
     }
-}
\ No newline at end of file
+
+    struct ContentBox<T> {
+        content: T,
+        host_info: Option<Box<dyn HostInfo>>,
+        externref_data: Weak<dyn InternalRefBase>,
+    }
+
+    impl<T> Drop for ContentBox<T> {
+        fn drop(&mut self) {
+            if let Some(info) = &mut self.host_info {
+                info.finalize();
+            }
+        }
+    }
+
+    /// Represents a piece of data located in the host environment.
+    pub struct HostRef<T>(Arc<TrustCell<ContentBox<T>>>);
+
+    impl<T: 'static + Send + Sync> HostRef<T> {
+        /// Creates a new `HostRef<T>` from `T`.
+        pub fn new(item: T) -> HostRef<T> {
+            let externref_data: Weak<HostRef<T>> = Weak::new();
+            let content = ContentBox {
+                content: item,
+                host_info: None,
+                externref_data,
+            };
+            HostRef(Arc::new(TrustCell::new(content)))
+        }
+
+        /// Immutably borrows the wrapped data, returning an error instead of
+        /// panicking if the value is currently mutably borrowed.
+        pub fn try_borrow(&self) -> Result<Ref<'_, T>, BorrowError> {
+            self.0
+                .try_borrow()
+                .map(|b| Ref::map(b, |b| &b.content))
+                .map_err(|_| BorrowError::new::<HostRef<T>>())
+        }
+
+        /// Immutably borrows the wrapped data.
+        /// # Panics
+        /// Panics if the value is currently mutably borrowed.
+        pub fn borrow(&self) -> Ref<'_, T> {
+            self.try_borrow().unwrap_or_else(|e| panic!("{}", e))
+        }
+
+        /// Mutably borrows the wrapped data, returning an error instead of
+        /// panicking if the `HostRef<T>` is already borrowed.
+        pub fn try_borrow_mut(&self) -> Result<RefMut<'_, T>, BorrowMutError> {
+            self.0
+                .try_borrow_mut()
+                .map(|b| RefMut::map(b, |b| &mut b.content))
+                .map_err(|_| BorrowMutError::new::<HostRef<T>>())
+        }
+
+        /// Mutably borrows the wrapped data.
+        /// # Panics
+        /// Panics if the `HostRef<T>` is already borrowed.
+        pub fn borrow_mut(&self) -> RefMut<'_, T> {
+            self.try_borrow_mut().unwrap_or_else(|e| panic!("{}", e))
+        }
+
+        /// Returns true if the two `HostRef<T>`'s point to the same value (not just
+        /// values that compare as equal).
+        pub fn ptr_eq(&self, other: &HostRef<T>) -> bool {
+            Arc::ptr_eq(&self.0, &other.0)
+        }
+
+        /// Returns an opaque reference to the wrapped data in the form of
+        /// an `ExternRef`.
+        /// # Panics
+        /// Panics if `HostRef<T>` is already borrowed.
+        pub fn externref(&self) -> ExternRef {
+            let r = self.0.borrow_mut().externref_data.upgrade();
+            if let Some(r) = r {
+                return ExternRef::Ref(InternalRef(r));
+            }
+            let externref_data: Arc<dyn InternalRefBase> = Arc::new(self.clone());
+            self.0.borrow_mut().externref_data = Arc::downgrade(&externref_data);
+            ExternRef::Ref(InternalRef(externref_data))
+        }
+    }
+
+    impl<T: 'static + Send + Sync> InternalRefBase for HostRef<T> {
+        fn ptr_eq(&self, other: &dyn InternalRefBase) -> bool {
+            if let Some(other) = other.as_any().downcast_ref() {
+                self.ptr_eq(other)
+            } else {
+                false
+            }
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn try_host_info(&self) -> Result<Option<RefMut<'_, Box<dyn HostInfo>>>, BorrowMutError> {
+            let info = RefMut::map(self.0.try_borrow_mut()?, |b| &mut b.host_info);
+            if info.is_none() {
+                return Ok(None);
+            }
+            Ok(Some(RefMut::map(info, |info| info.as_mut().unwrap())))
+        }
+
+        fn try_set_host_info(&self, info: Option<Box<dyn HostInfo>>) -> Result<(), BorrowMutError> {
+            let mut content = self.0.try_borrow_mut()?;
+            if let Some(old) = &mut content.host_info {
+                old.finalize();
+            }
+            content.host_info = info;
+            Ok(())
+        }
+    }
+
+    impl<T> Clone for HostRef<T> {
+        fn clone(&self) -> HostRef<T> {
+            HostRef(self.0.clone())
+        }
+    }
+
+    impl<T: fmt::Debug> fmt::Debug for HostRef<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "Ref(")?;
+            self.0.borrow().content.fmt(f)?;
+            write!(f, ")")
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::sync::Barrier;
+        use std::thread;
+
+        #[test]
+        fn concurrent_shared_borrows_succeed() {
+            let cell = Arc::new(TrustCell::new(1));
+            let barrier = Arc::new(Barrier::new(2));
+
+            let threads: Vec<_> = (0..2)
+                .map(|_| {
+                    let cell = cell.clone();
+                    let barrier = barrier.clone();
+                    thread::spawn(move || {
+                        let guard = cell.try_borrow().expect("shared borrows don't conflict");
+                        // Hold both guards open at once so this only passes if
+                        // they're genuinely concurrent, not just uncontended.
+                        barrier.wait();
+                        assert_eq!(*guard, 1);
+                    })
+                })
+                .collect();
+
+            for t in threads {
+                t.join().unwrap();
+            }
+            assert_eq!(cell.flag.load(Ordering::Acquire), 0);
+        }
+
+        #[test]
+        fn borrow_mut_fails_while_shared_borrow_is_live() {
+            let cell = TrustCell::new(1);
+            let _guard = cell.try_borrow().unwrap();
+            assert!(cell.try_borrow_mut().is_err());
+        }
+
+        #[test]
+        fn shared_borrow_fails_while_mutably_borrowed() {
+            let cell = TrustCell::new(1);
+            let _guard = cell.try_borrow_mut().unwrap();
+            assert!(cell.try_borrow().is_err());
+        }
+
+        #[test]
+        fn map_split_mutable_borrow_stays_writing_until_both_halves_drop() {
+            let cell = TrustCell::new((1, 2));
+            let guard = cell.try_borrow_mut().unwrap();
+            let (a, b) = RefMut::map_split(guard, |pair| (&mut pair.0, &mut pair.1));
+
+            assert_eq!(cell.flag.load(Ordering::Acquire), WRITING);
+            assert!(cell.try_borrow_mut().is_err());
+
+            drop(a);
+            assert_eq!(
+                cell.flag.load(Ordering::Acquire),
+                WRITING,
+                "flag must stay WRITING until every split guard has dropped"
+            );
+            assert!(cell.try_borrow_mut().is_err());
+
+            drop(b);
+            assert_eq!(cell.flag.load(Ordering::Acquire), 0);
+            assert!(cell.try_borrow_mut().is_ok());
+        }
+    }
+}